@@ -1,10 +1,11 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_sdk::collections::Map;
 use near_sdk::json_types::{Base58PublicKey, U128};
+use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    env, ext_contract, near_bindgen, AccountId, Balance, Promise, PublicKey,
+    env, ext_contract, near_bindgen, AccountId, Balance, Promise, PromiseOrValue, PromiseResult,
+    PublicKey,
 };
-use std::convert::TryInto;
 
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
@@ -18,6 +19,22 @@ pub struct RedInfo {
     pub slogan: String, // 口号
     pub balance: Balance, // 总金额
     pub remaining_balance: u128, // 红包剩余金额
+    pub remaining_count: u128, // 红包剩余可领取份数
+    pub token_id: Option<AccountId>, // 红包计价代币，None 表示原生 NEAR，Some 表示对应 NEP-141 合约账户
+    pub expires_at: Option<u64>, // 红包过期时间（纳秒时间戳），None 表示永不过期
+    pub messages: Vec<Vec<u8>>, // 祝福语分片原文（已去除 cookie/序号/长度头，可为客户端加密后的密文），按序号排列
+}
+
+/// `ft_on_transfer` 附带的 `msg` 反序列化结构，描述用代币创建的红包参数
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtRedbagMsg {
+    pub public_key: Base58PublicKey,
+    pub count: u128,
+    pub mode: u8,
+    pub slogan: String,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
 }
 
 #[derive(Clone)]
@@ -30,6 +47,48 @@ pub struct ReceivedRedInfo {
 
 pub type RedInfoKey = Vec<u8>;
 
+/// 多签配置：signers 为授权签名人公钥集合，threshold 为执行所需的最少批准数
+#[derive(Clone)]
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MultisigConfig {
+    pub signers: Vec<PublicKey>,
+    pub threshold: u32,
+}
+
+/// 待多签批准的操作内容
+#[derive(Clone)]
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum PendingAction {
+    /// 撤回红包剩余金额并删除红包、回收访问密钥
+    Revoke { public_key: Base58PublicKey },
+    /// 红包过期后取回剩余金额并删除红包、回收访问密钥
+    RefundExpired { public_key: Base58PublicKey },
+    /// 创建一个超过多签门槛金额的红包；token_id 为 None 表示原生 NEAR，Some 表示 NEP-141 代币
+    SendRedbag {
+        public_key: Base58PublicKey,
+        count: u128,
+        mode: u8,
+        slogan: String,
+        expires_at: Option<u64>,
+        deposit: Balance,
+        token_id: Option<AccountId>,
+    },
+    /// 修改发送人自己的多签规则（signers/threshold）
+    SetConfig { signers: Vec<PublicKey>, threshold: u32 },
+}
+
+/// 待执行的多签提案及其已收到的批准
+#[derive(Clone)]
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PendingApproval {
+    pub sender_id: AccountId,
+    pub action: PendingAction,
+    pub config: MultisigConfig,
+    pub approvals: Vec<PublicKey>,
+}
+
+pub type ActionHash = Vec<u8>;
+
 #[near_bindgen]
 #[derive(Default, BorshDeserialize, BorshSerialize)]
 pub struct LinkDrop {
@@ -44,6 +103,10 @@ pub struct LinkDrop {
     pub red_receive_detail: Map<(PublicKey, AccountId), u128>, // 红包领取详细信息（红包、领取人、领取数量）
 
     pub receiver_redbag_record: Map<AccountId, Vec<ReceivedRedInfo>>, // 用户所领取的红包
+
+    pub multisig_config: Map<AccountId, MultisigConfig>, // 发送人配置的多签规则
+
+    pub pending_actions: Map<ActionHash, PendingApproval>, // 等待多签批准的操作
 }
 
 /// Access key allowance for linkdrop keys.
@@ -55,6 +118,37 @@ pub const ON_CREATE_ACCOUNT_CALLBACK_GAS: u64 = 20_000_000_000_000;
 /// Indicates there are no deposit for a callback for better readability.
 const NO_DEPOSIT: u128 = 0;
 
+/// 随机红包每个领取人最低可得金额(yoctoNEAR)，为后续待领取人预留份额，避免红包被提前瓜分殆尽
+const MIN_CLAIM_AMOUNT: u128 = 1;
+
+/// NEP-141 `ft_transfer` 要求附带 1 yoctoNEAR 以防止全额存储支付攻击
+const ONE_YOCTO: u128 = 1;
+
+/// Gas attached to the cross-contract `ft_transfer` call.
+pub const GAS_FOR_FT_TRANSFER: u64 = 10_000_000_000_000;
+
+/// Gas attached to the callback that checks the `ft_transfer` result.
+pub const GAS_FOR_FT_TRANSFER_CALLBACK: u64 = 10_000_000_000_000;
+
+/// 祝福语分片头部的版本 cookie，用于客户端校验分片来源与格式版本
+const MESSAGE_CHUNK_COOKIE: [u8; 4] = *b"RDPK";
+
+/// 单个祝福语分片允许的最大载荷长度（字节）
+const MESSAGE_CHUNK_MAX_PAYLOAD: usize = 511;
+
+/// 分片头部长度：4 字节 cookie + 1 字节序号 + 2 字节长度
+const MESSAGE_CHUNK_HEADER_LEN: usize = 7;
+
+/// 单个红包允许附加的最大祝福语分片数，避免无限占用合约自身的持久化存储
+const MESSAGE_CHUNK_MAX_COUNT: usize = 32;
+
+/// 每字节存储成本（yoctoNEAR），attach_message 按新增分片字节数向调用者收取押金，
+/// 不应由合约自身余额承担这部分持久化存储开销
+const STORAGE_PRICE_PER_BYTE: Balance = 10_000_000_000_000_000_000;
+
+/// 发送人配置了多签规则时，创建红包若金额达到该门槛（100 NEAR）则需 M-of-N 批准后才会真正创建
+const MULTISIG_BALANCE_THRESHOLD: u128 = 100_000_000_000_000_000_000_000_000;
+
 #[ext_contract(ext_self)]
 pub trait ExtLinkDrop {
     /// Callback after plain account creation.
@@ -62,6 +156,24 @@ pub trait ExtLinkDrop {
 
     /// Callback after creating account and claiming linkdrop.
     fn on_account_created_and_claimed(&mut self, amount: U128) -> bool;
+
+    /// Callback after a NEP-141 `ft_transfer`, restores the red packet balance on failure.
+    fn on_ft_transfer_complete(&mut self, public_key: PublicKey, account_id: AccountId, amount: U128) -> bool;
+
+    /// Callback after the `ft_transfer` issued by revoke/refund_expired, only deletes the red
+    /// packet record once the refund is confirmed; on failure the record is kept so the sender
+    /// can retry instead of losing the remaining token balance.
+    fn on_refund_transfer_complete(&mut self, sender_id: AccountId, public_key: PublicKey) -> bool;
+}
+
+#[ext_contract(ext_fungible_token)]
+pub trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+/// 与 NEP-141 `FungibleTokenReceiver` 标准对齐的回调接口
+pub trait FungibleTokenReceiver {
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128>;
 }
 
 #[near_bindgen]
@@ -69,11 +181,45 @@ impl LinkDrop {
 
     ///  发（创建）红包功能
     #[payable]
-    pub fn send_redbag(&mut self, public_key: Base58PublicKey, count: u128, mode: u8, slogan: String) -> Promise {
+    pub fn send_redbag(
+        &mut self,
+        public_key: Base58PublicKey,
+        count: u128,
+        mode: u8,
+        slogan: String,
+        expires_at: Option<u64>,
+    ) -> Promise {
         assert!(
             env::attached_deposit() > ACCESS_KEY_ALLOWANCE,
             "Attached deposit must be greater than ACCESS_KEY_ALLOWANCE"
         );
+        if let Some(expires_at) = expires_at {
+            assert!(expires_at > env::block_timestamp(), "expires_at must be in the future");
+        }
+
+        let deposit = env::attached_deposit();
+        let sender_id = env::signer_account_id();
+        assert!(
+            count >= 1 && count <= deposit,
+            "count must be between 1 and the attached deposit"
+        );
+
+        // 大额红包若发送人配置了多签，则转为提案，等待 M-of-N 批准后才真正创建
+        if deposit >= MULTISIG_BALANCE_THRESHOLD {
+            if let Some(config) = self.multisig_config.get(&sender_id) {
+                let action = PendingAction::SendRedbag {
+                    public_key,
+                    count,
+                    mode,
+                    slogan,
+                    expires_at,
+                    deposit,
+                    token_id: None,
+                };
+                self.propose_action(sender_id, action, config);
+                return Promise::new(env::current_account_id());
+            }
+        }
 
         let pk = public_key.clone().into();
 
@@ -82,17 +228,21 @@ impl LinkDrop {
             mode: mode,
             count: count,
             slogan: slogan,
-            balance: env::attached_deposit(),
-            remaining_balance: env::attached_deposit(),
+            balance: deposit,
+            remaining_balance: deposit,
+            remaining_count: count,
+            token_id: None,
+            expires_at: expires_at,
+            messages: Vec::new(),
         };
 
-        assert!(self.red_info.get(&pk).is_none(), "existed");
+        self.register_redbag(sender_id, public_key.clone(), new_red_info);
 
-        self.red_info.insert(&pk, &new_red_info);
-        let mut relation_vec = self.sender_redbag.get(&env::signer_account_id()).unwrap_or(Vec::new());
-        relation_vec.push(public_key.clone());
-        self.sender_redbag.insert(&env::signer_account_id(), &relation_vec);
+        self.grant_claim_access_key(pk)
+    }
 
+    /// 为 public_key 颁发可调用 create_account_and_claim/claim/revoke 的访问密钥
+    fn grant_claim_access_key(&self, pk: PublicKey) -> Promise {
         Promise::new(env::current_account_id()).add_access_key(
             pk,
             ACCESS_KEY_ALLOWANCE,
@@ -101,6 +251,17 @@ impl LinkDrop {
         )
     }
 
+    /// 将红包信息与发送人关联关系落盘，供原生 NEAR 红包与 NEP-141 代币红包共用
+    fn register_redbag(&mut self, sender_id: AccountId, public_key: Base58PublicKey, red_info: RedInfo) {
+        let pk: PublicKey = public_key.clone().into();
+        assert!(self.red_info.get(&pk).is_none(), "existed");
+
+        self.red_info.insert(&pk, &red_info);
+        let mut relation_vec = self.sender_redbag.get(&sender_id).unwrap_or(Vec::new());
+        relation_vec.push(public_key);
+        self.sender_redbag.insert(&sender_id, &relation_vec);
+    }
+
     /// 创建新用户并同时领取红包
     pub fn create_account_and_claim(
         &mut self,
@@ -115,21 +276,25 @@ impl LinkDrop {
 
         // 查看红包剩余数量是否可被领取
         let temp_redbag = &redbag.unwrap();
-        let count = temp_redbag.count;
         let remaining_balance = temp_redbag.remaining_balance;
+        let remaining_count = temp_redbag.remaining_count;
+        assert!(remaining_count > 0, "红包已被领取完");
 
-        let mut record = self.red_receive_record.get(&pk).unwrap_or(Vec::new());
-        assert!(record.len() < count.try_into().unwrap(), "红包已被领取完");
+        if let Some(expires_at) = temp_redbag.expires_at {
+            assert!(env::block_timestamp() < expires_at, "红包已过期");
+        }
 
+        let mut record = self.red_receive_record.get(&pk).unwrap_or(Vec::new());
         record.push(String::from(&new_account_id));
         self.red_receive_record.insert(&pk, &record);
 
-        self.red_receive_detail.insert(&(pk.clone().into(), new_account_id.clone()), &count);
-
         // 分配红包
         let mut receiver_record = self.receiver_redbag_record.get(&new_account_id).unwrap_or(Vec::new());
 
-        let amount: Balance = self.random_amount(remaining_balance);
+        let amount: Balance = self.random_amount(temp_redbag.mode, remaining_balance, remaining_count);
+        let token_id = temp_redbag.token_id.clone();
+
+        self.red_receive_detail.insert(&(pk.clone().into(), new_account_id.clone()), &amount);
 
         let received_redbag_info = ReceivedRedInfo {
             amount: amount,
@@ -145,21 +310,46 @@ impl LinkDrop {
             slogan: temp_redbag.clone().slogan,
             balance: temp_redbag.clone().balance,
             remaining_balance: temp_redbag.clone().remaining_balance - amount,
+            remaining_count: remaining_count - 1,
+            token_id: token_id.clone(),
+            expires_at: temp_redbag.expires_at,
+            messages: temp_redbag.messages.clone(),
         };
 
         self.red_info.insert(&pk, &new_red_info);
 
-        Promise::new(new_account_id)
-            .create_account()
-            .add_full_access_key(new_public_key.into())
-            .transfer(amount)
-            .then(ext_self::on_account_created(
-                env::predecessor_account_id(),
-                amount.into(),
-                &env::current_account_id(),
-                NO_DEPOSIT,
-                ON_CREATE_ACCOUNT_CALLBACK_GAS,
-            ))
+        match token_id {
+            None => Promise::new(new_account_id)
+                .create_account()
+                .add_full_access_key(new_public_key.into())
+                .transfer(amount)
+                .then(ext_self::on_account_created(
+                    env::predecessor_account_id(),
+                    amount.into(),
+                    &env::current_account_id(),
+                    NO_DEPOSIT,
+                    ON_CREATE_ACCOUNT_CALLBACK_GAS,
+                )),
+            Some(token_id) => Promise::new(new_account_id.clone())
+                .create_account()
+                .add_full_access_key(new_public_key.into())
+                .then(ext_fungible_token::ft_transfer(
+                    new_account_id.clone(),
+                    amount.into(),
+                    None,
+                    &token_id,
+                    ONE_YOCTO,
+                    GAS_FOR_FT_TRANSFER,
+                ))
+                .then(ext_self::on_ft_transfer_complete(
+                    pk,
+                    new_account_id,
+                    amount.into(),
+                    &env::current_account_id(),
+                    NO_DEPOSIT,
+                    GAS_FOR_FT_TRANSFER_CALLBACK,
+                )),
+        }
     }
 
     /// 领取红包
@@ -172,24 +362,31 @@ impl LinkDrop {
 
         // 查看红包剩余数量是否可被领取
         let temp_redbag = &redbag.unwrap();
-        let count = temp_redbag.count;
         let remaining_balance = temp_redbag.remaining_balance;
-        let mut record = self.red_receive_record.get(&pk).unwrap_or(Vec::new());
-        assert!(record.len() < count.try_into().unwrap(), "红包已被领取完");
+        let remaining_count = temp_redbag.remaining_count;
+        assert!(remaining_count > 0, "红包已被领取完");
 
-        // 判断用户手否领取过
-        for x in &record {
-            assert!(String::from(x) != account_id, "该用户已领取过");
+        if let Some(expires_at) = temp_redbag.expires_at {
+            assert!(env::block_timestamp() < expires_at, "红包已过期");
         }
 
+        // O(1) 判断用户是否领取过：复用 red_receive_detail 的键是否存在，无需扫描整个领取记录
+        assert!(
+            self.red_receive_detail.get(&(pk.clone().into(), account_id.clone())).is_none(),
+            "该用户已领取过"
+        );
+
+        let mut record = self.red_receive_record.get(&pk).unwrap_or(Vec::new());
         record.push(String::from(&account_id));
         self.red_receive_record.insert(&pk, &record);
-        self.red_receive_detail.insert(&(pk.clone().into(), account_id.clone()), &count);
 
         // 分配红包
         let mut receiver_record = self.receiver_redbag_record.get(&account_id).unwrap_or(Vec::new());
 
-        let amount: Balance = self.random_amount(remaining_balance);
+        let amount: Balance = self.random_amount(temp_redbag.mode, remaining_balance, remaining_count);
+        let token_id = temp_redbag.token_id.clone();
+
+        self.red_receive_detail.insert(&(pk.clone().into(), account_id.clone()), &amount);
 
         let received_redbag_info = ReceivedRedInfo {
             amount: amount,
@@ -205,31 +402,374 @@ impl LinkDrop {
             slogan: temp_redbag.clone().slogan,
             balance: temp_redbag.clone().balance,
             remaining_balance: temp_redbag.clone().remaining_balance - amount,
+            remaining_count: remaining_count - 1,
+            token_id: token_id.clone(),
+            expires_at: temp_redbag.expires_at,
+            messages: temp_redbag.messages.clone(),
         };
 
         self.red_info.insert(&pk, &new_red_info);
 
         // 减少红包数量及金额
-        Promise::new(account_id).transfer(amount)
+        match token_id {
+            None => Promise::new(account_id).transfer(amount),
+            Some(token_id) => ext_fungible_token::ft_transfer(
+                account_id.clone(),
+                amount.into(),
+                None,
+                &token_id,
+                ONE_YOCTO,
+                GAS_FOR_FT_TRANSFER,
+            )
+            .then(ext_self::on_ft_transfer_complete(
+                pk,
+                account_id,
+                amount.into(),
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_FT_TRANSFER_CALLBACK,
+            )),
+        }
     }
 
-    /// 发红包任用来撤回对应public_key的红包剩余金额
-    pub fn revoke(&mut self, public_key: Base58PublicKey) -> &str {
-        let pk = public_key.clone().into();
-        self.red_info.remove(&pk);
-        let mut red_list = self.sender_redbag.get(&env::signer_account_id()).unwrap();
+    /// `ft_transfer` 跨合约调用结束后的回调，若转账失败则把金额和名额退回红包
+    pub fn on_ft_transfer_complete(&mut self, public_key: PublicKey, account_id: AccountId, amount: U128) -> bool {
+        assert_eq!(env::promise_results_count(), 1, "预期只有一个 promise 结果");
+
+        let transfer_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+
+        if !transfer_succeeded {
+            if let Some(mut red_info) = self.red_info.get(&public_key) {
+                red_info.remaining_balance += amount.0;
+                red_info.remaining_count += 1;
+                self.red_info.insert(&public_key, &red_info);
+            }
+
+            self.red_receive_detail.remove(&(public_key.clone().into(), account_id.clone()));
+
+            if let Some(mut record) = self.red_receive_record.get(&public_key) {
+                record.retain(|x| x != &account_id);
+                self.red_receive_record.insert(&public_key, &record);
+            }
+
+            // 转账失败，claim/create_account_and_claim 中记下的“已领取”条目也要撤销，避免重复计入
+            let redbag_key = Base58PublicKey(public_key.clone());
+            if let Some(mut received) = self.receiver_redbag_record.get(&account_id) {
+                received.retain(|r| !(r.redbag == redbag_key && r.amount == amount.0));
+                self.receiver_redbag_record.insert(&account_id, &received);
+            }
+        }
+
+        transfer_succeeded
+    }
+
+    /// 发红包任用来撤回对应public_key的红包剩余金额；配置了多签的发送人须改用 propose_revoke + approve
+    pub fn revoke(&mut self, public_key: Base58PublicKey) -> Promise {
+        let sender_id = env::signer_account_id();
+        assert!(
+            self.multisig_config.get(&sender_id).is_none(),
+            "已配置多签，请使用 propose_revoke 和 approve 完成撤回"
+        );
+
+        let sent_list = self.sender_redbag.get(&sender_id).unwrap_or(Vec::new());
+        assert!(sent_list.iter().any(|x| x == &public_key), "非该红包发送人");
+
+        self.remove_and_refund(sender_id, public_key)
+    }
+
+    /// 配置发送人自己的多签规则：signers 为授权签名人公钥集合，threshold 为执行所需的最少批准数。
+    /// 首次配置（当前尚无规则）直接生效并返回 None；修改已有规则必须经由现有规则本身的 M-of-N 批准，
+    /// 返回 Some(action_hash) 供各签名人 approve，否则单个密钥就能篡改 signers/threshold 使多签形同虚设
+    pub fn set_multisig_config(&mut self, signers: Vec<Base58PublicKey>, threshold: u32) -> Option<ActionHash> {
+        assert!(
+            threshold > 0 && (threshold as usize) <= signers.len(),
+            "threshold 必须在 1..=signers.len() 范围内"
+        );
+
+        let signers: Vec<PublicKey> = signers.into_iter().map(Into::into).collect();
+        let sender_id = env::signer_account_id();
+
+        match self.multisig_config.get(&sender_id) {
+            None => {
+                self.multisig_config.insert(&sender_id, &MultisigConfig { signers, threshold });
+                None
+            }
+            Some(current_config) => {
+                let action = PendingAction::SetConfig { signers, threshold };
+                Some(self.propose_action(sender_id, action, current_config))
+            }
+        }
+    }
+
+    /// 为“撤回红包”发起多签提案，需由已配置多签的发送人调用，返回的 action_hash 供各签名人 approve
+    pub fn propose_revoke(&mut self, public_key: Base58PublicKey) -> ActionHash {
+        let sender_id = env::signer_account_id();
+        let config = self.multisig_config.get(&sender_id).expect("未配置多签");
+
+        let sent_list = self.sender_redbag.get(&sender_id).unwrap_or(Vec::new());
+        assert!(sent_list.iter().any(|x| x == &public_key), "非该红包发送人");
+
+        self.propose_action(sender_id, PendingAction::Revoke { public_key }, config)
+    }
+
+    /// 为“取回过期红包剩余金额”发起多签提案，需由已配置多签的发送人调用，返回的 action_hash 供各签名人 approve
+    pub fn propose_refund_expired(&mut self, public_key: Base58PublicKey) -> ActionHash {
+        let sender_id = env::signer_account_id();
+        let config = self.multisig_config.get(&sender_id).expect("未配置多签");
+
+        self.assert_sender_and_expired(&sender_id, &public_key);
+
+        self.propose_action(sender_id, PendingAction::RefundExpired { public_key }, config)
+    }
+
+    /// 授权签名人对某个多签提案投出批准票；达到 threshold 后立即执行该操作。
+    /// 每次都以发起人*当前*的 multisig_config 重新校验，而不是提案创建时的快照，
+    /// 避免提案挂起期间被 set_multisig_config 轮换掉的签名人、旧 threshold 仍然生效；
+    /// 被移出 signers 的人此前投的票也一并作废，必须用新规则下的签名人重新凑够 threshold
+    pub fn approve(&mut self, action_hash: ActionHash) -> Promise {
+        let signer_pk = env::signer_account_pk();
+        let mut pending = self.pending_actions.get(&action_hash).expect("提案不存在");
+
+        pending.config = self.multisig_config.get(&pending.sender_id).expect("多签规则已被移除");
+        pending.approvals.retain(|pk| pending.config.signers.contains(pk));
+
+        assert!(pending.config.signers.contains(&signer_pk), "不是该提案的授权签名人");
+        assert!(!pending.approvals.contains(&signer_pk), "该签名人已批准过");
+
+        pending.approvals.push(signer_pk);
+
+        if (pending.approvals.len() as u32) < pending.config.threshold {
+            self.pending_actions.insert(&action_hash, &pending);
+            return Promise::new(env::current_account_id());
+        }
+
+        self.pending_actions.remove(&action_hash);
+        self.execute_action(pending.sender_id, pending.action)
+    }
+
+    /// 撤销发起人自己提出、尚未达到 threshold 执行的多签提案；若为 SendRedbag 提案会把押金退回发起人，
+    /// 否则已批准过的信号石沉大海时押金会被永久锁死在合约里（比不配置多签时发送人还能 revoke 更糟）
+    pub fn cancel_action(&mut self, action_hash: ActionHash) -> Promise {
+        let sender_id = env::signer_account_id();
+        let pending = self.pending_actions.get(&action_hash).expect("提案不存在");
+        assert_eq!(pending.sender_id, sender_id, "只有提案发起人可撤销");
+
+        self.pending_actions.remove(&action_hash);
+
+        match pending.action {
+            PendingAction::SendRedbag { deposit, token_id, .. } => match token_id {
+                None => Promise::new(sender_id).transfer(deposit),
+                Some(token_id) => ext_fungible_token::ft_transfer(
+                    sender_id,
+                    deposit.into(),
+                    None,
+                    &token_id,
+                    ONE_YOCTO,
+                    GAS_FOR_FT_TRANSFER,
+                ),
+            },
+            PendingAction::Revoke { .. }
+            | PendingAction::RefundExpired { .. }
+            | PendingAction::SetConfig { .. } => Promise::new(env::current_account_id()),
+        }
+    }
+
+    /// 记录一个新的多签提案，key 为发送人 + 操作内容的哈希
+    fn propose_action(&mut self, sender_id: AccountId, action: PendingAction, config: MultisigConfig) -> ActionHash {
+        let action_hash = Self::hash_action(&sender_id, &action);
+        assert!(self.pending_actions.get(&action_hash).is_none(), "该操作已被提出");
+
+        self.pending_actions.insert(
+            &action_hash,
+            &PendingApproval { sender_id, action, config, approvals: Vec::new() },
+        );
+
+        action_hash
+    }
 
-        let mut index = 0;
-        for item in red_list.clone().iter() {
-            if item == &public_key {
-                break;
+    /// 多签批准达到 threshold 后，真正执行被提议的操作
+    fn execute_action(&mut self, sender_id: AccountId, action: PendingAction) -> Promise {
+        match action {
+            PendingAction::Revoke { public_key } => self.remove_and_refund(sender_id, public_key),
+            PendingAction::RefundExpired { public_key } => self.remove_and_refund(sender_id, public_key),
+            PendingAction::SendRedbag { public_key, count, mode, slogan, expires_at, deposit, token_id } => {
+                assert!(
+                    count >= 1 && count <= deposit,
+                    "count must be between 1 and the attached deposit"
+                );
+
+                let new_red_info = RedInfo {
+                    mode,
+                    count,
+                    slogan,
+                    balance: deposit,
+                    remaining_balance: deposit,
+                    remaining_count: count,
+                    token_id,
+                    expires_at,
+                    messages: Vec::new(),
+                };
+
+                self.register_redbag(sender_id, public_key.clone(), new_red_info);
+                self.grant_claim_access_key(public_key.into())
             }
-            index += 1;
+            PendingAction::SetConfig { signers, threshold } => {
+                self.multisig_config.insert(&sender_id, &MultisigConfig { signers, threshold });
+                Promise::new(env::current_account_id())
+            }
+        }
+    }
+
+    /// 计算多签提案的 action_hash：对发送人与操作内容序列化后取 sha256
+    fn hash_action(sender_id: &AccountId, action: &PendingAction) -> ActionHash {
+        let mut bytes = sender_id.as_bytes().to_vec();
+        bytes.extend(action.try_to_vec().unwrap());
+        env::sha256(&bytes)
+    }
+
+    /// 红包过期后，由发送人调用以取回剩余金额、删除红包信息并回收访问密钥；
+    /// 配置了多签的发送人须改用 propose_refund_expired + approve，理由同 revoke
+    pub fn refund_expired(&mut self, public_key: Base58PublicKey) -> Promise {
+        let sender_id = env::signer_account_id();
+        assert!(
+            self.multisig_config.get(&sender_id).is_none(),
+            "已配置多签，请使用 propose_refund_expired 和 approve 完成退款"
+        );
+
+        self.assert_sender_and_expired(&sender_id, &public_key);
+
+        self.remove_and_refund(sender_id, public_key)
+    }
+
+    /// 校验调用者确为该红包发送人，且红包已过期
+    fn assert_sender_and_expired(&self, sender_id: &AccountId, public_key: &Base58PublicKey) {
+        let sent_list = self.sender_redbag.get(sender_id).unwrap_or(Vec::new());
+        assert!(sent_list.iter().any(|x| x == public_key), "非该红包发送人");
+
+        let pk: PublicKey = public_key.clone().into();
+        let red_info = self.red_info.get(&pk).expect("红包不存在");
+        let expires_at = red_info.expires_at.expect("红包未设置过期时间");
+        assert!(env::block_timestamp() >= expires_at, "红包尚未过期");
+    }
+
+    /// 回收某 public_key 对应的访问密钥，并把剩余金额（或 NEP-141 代币）退还给 sender_id；
+    /// 由 revoke、refund_expired 及其多签提案执行路径共用。原生 NEAR 可直接退款并立即删除红包记录；
+    /// NEP-141 代币须等 ft_transfer 的回调确认成功后才删除记录，避免转账失败时代币无法追回
+    fn remove_and_refund(&mut self, sender_id: AccountId, public_key: Base58PublicKey) -> Promise {
+        let pk: PublicKey = public_key.clone().into();
+        let red_info = self.red_info.get(&pk).expect("红包不存在");
+
+        let remaining_balance = red_info.remaining_balance;
+        let token_id = red_info.token_id.clone();
+
+        match token_id {
+            None => {
+                self.remove_redbag_record(&sender_id, &public_key);
+                Promise::new(env::current_account_id())
+                    .delete_key(pk)
+                    .and(Promise::new(sender_id).transfer(remaining_balance))
+            }
+            Some(token_id) => {
+                // 访问密钥单独回收，回调只依赖 ft_transfer 这一个 promise 结果
+                Promise::new(env::current_account_id()).delete_key(pk.clone());
+                ext_fungible_token::ft_transfer(
+                    sender_id.clone(),
+                    remaining_balance.into(),
+                    None,
+                    &token_id,
+                    ONE_YOCTO,
+                    GAS_FOR_FT_TRANSFER,
+                )
+                .then(ext_self::on_refund_transfer_complete(
+                    sender_id,
+                    pk,
+                    &env::current_account_id(),
+                    NO_DEPOSIT,
+                    GAS_FOR_FT_TRANSFER_CALLBACK,
+                ))
+            }
+        }
+    }
+
+    /// `ft_transfer` 退款结束后的回调：成功才真正删除红包记录；失败则保留记录，
+    /// 发送人可再次调用 revoke/refund_expired（或多签提案）重试退款
+    pub fn on_refund_transfer_complete(&mut self, sender_id: AccountId, public_key: PublicKey) -> bool {
+        assert_eq!(env::promise_results_count(), 1, "预期只有一个 promise 结果");
+
+        let transfer_succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+
+        if transfer_succeeded {
+            self.remove_redbag_record(&sender_id, &Base58PublicKey(public_key));
+        }
+
+        transfer_succeeded
+    }
+
+    /// 从红包信息表与发送人关联表中移除对应 public_key 的红包记录
+    fn remove_redbag_record(&mut self, sender_id: &AccountId, public_key: &Base58PublicKey) {
+        let pk: PublicKey = public_key.clone().into();
+        self.red_info.remove(&pk);
+
+        let mut red_list = self.sender_redbag.get(sender_id).unwrap_or(Vec::new());
+        if let Some(index) = red_list.iter().position(|item| item == public_key) {
+            red_list.remove(index);
+        }
+        self.sender_redbag.insert(sender_id, &red_list);
+    }
+
+    /// 为红包附加祝福语分片，chunks 中每个分片需自带 cookie/序号/长度头，按序号顺序提交；
+    /// 内容可以是明文，也可以是客户端用 ChaCha20-Poly1305 等算法加密好的密文，合约仅透传存储。
+    /// 分片数量有上限，且新增存储字节需由调用者的附加押金覆盖，避免合约自身余额被白占用
+    #[payable]
+    pub fn attach_message(&mut self, public_key: Base58PublicKey, chunks: Vec<Vec<u8>>) {
+        assert!(chunks.len() <= MESSAGE_CHUNK_MAX_COUNT, "祝福语分片数量超出上限");
+
+        let pk: PublicKey = public_key.clone().into();
+        let mut red_info = self.red_info.get(&pk).expect("红包不存在");
+
+        let sender_id = env::signer_account_id();
+        let sent_list = self.sender_redbag.get(&sender_id).unwrap_or(Vec::new());
+        assert!(sent_list.iter().any(|x| x == &public_key), "非该红包发送人");
+
+        let new_messages: Vec<Vec<u8>> = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, chunk)| Self::decode_message_chunk(chunk, index as u8))
+            .collect();
+
+        let old_bytes: usize = red_info.messages.iter().map(Vec::len).sum();
+        let new_bytes: usize = new_messages.iter().map(Vec::len).sum();
+        if new_bytes > old_bytes {
+            let required_deposit = (new_bytes - old_bytes) as Balance * STORAGE_PRICE_PER_BYTE;
+            assert!(env::attached_deposit() >= required_deposit, "附加存储押金不足");
         }
 
-        red_list.remove(index);
-        self.sender_redbag.insert(&env::signer_account_id(), &red_list);
-        "revoke success"
+        red_info.messages = new_messages;
+        self.red_info.insert(&pk, &red_info);
+    }
+
+    /// 读取并拼接某红包已附加的祝福语（原样返回，若分片为密文则由客户端自行解密）
+    pub fn read_message(self, public_key: Base58PublicKey) -> Vec<u8> {
+        let pk: PublicKey = public_key.into();
+        let red_info = self.red_info.get(&pk).expect("红包不存在");
+        red_info.messages.concat()
+    }
+
+    /// 校验并解出单个祝福语分片的载荷：4 字节 cookie + 1 字节序号 + 2 字节长度(大端) + 载荷
+    fn decode_message_chunk(chunk: &[u8], expected_index: u8) -> Vec<u8> {
+        assert!(chunk.len() >= MESSAGE_CHUNK_HEADER_LEN, "消息分片头部长度不足");
+        assert_eq!(&chunk[0..4], &MESSAGE_CHUNK_COOKIE, "消息分片 cookie 不匹配");
+
+        let index = chunk[4];
+        assert_eq!(index, expected_index, "消息分片顺序错误");
+
+        let length = u16::from_be_bytes([chunk[5], chunk[6]]) as usize;
+        assert!(length <= MESSAGE_CHUNK_MAX_PAYLOAD, "消息分片超出最大长度");
+
+        let payload = &chunk[MESSAGE_CHUNK_HEADER_LEN..];
+        assert_eq!(payload.len(), length, "消息分片长度与头部声明不符");
+
+        payload.to_vec()
     }
 
     /// 查询用户发的红包
@@ -258,36 +798,397 @@ impl LinkDrop {
         relation_vec
     }
 
-    /// 生成随机
-    fn random_amount(&self, total_amount: u128) -> u128 {
-        let u8_max_value: u128 = u8::max_value().into();
-        let block_length = total_amount / u8_max_value;
+    /// 计算本次应分配的红包金额，采用微信双均值算法：
+    /// 拆剩 remaining_count 份、剩余 remaining_balance 金额时，
+    /// 在 [MIN_CLAIM_AMOUNT, 2 * (remaining_balance / remaining_count)] 区间内均匀取值，
+    /// 保证每人必得且总额不变；均分模式(mode == 0)则按剩余份数平均分配；
+    /// 最后一人直接拿走全部剩余金额。
+    fn random_amount(&self, mode: u8, remaining_balance: u128, remaining_count: u128) -> u128 {
+        if remaining_count <= 1 {
+            return remaining_balance;
+        }
+
+        if mode == 0 {
+            return remaining_balance / remaining_count;
+        }
+
+        // 为余下的领取人预留最低金额，防止红包被提前瓜分殆尽
+        let reserved_for_others = MIN_CLAIM_AMOUNT * (remaining_count - 1);
+        let max_amount = remaining_balance - reserved_for_others;
+
+        let fair_ceiling = 2 * (remaining_balance / remaining_count);
+        let ceiling = std::cmp::min(fair_ceiling, max_amount);
+        let range_width = if ceiling > MIN_CLAIM_AMOUNT {
+            ceiling - MIN_CLAIM_AMOUNT + 1
+        } else {
+            1
+        };
 
-        let random_seed = env::random_seed();
+        let random_value = Self::fold_random_seed();
+        let amount = MIN_CLAIM_AMOUNT + random_value % range_width;
 
-        // 计算总 seed 值
-        let mut block_index = 0_u8;
+        std::cmp::min(amount, max_amount)
+    }
 
-        for item in random_seed {
-            block_index = block_index.wrapping_add(item);
+    /// 将 env::random_seed() 的字节折叠为一个 u128，作为均匀取值的随机源
+    fn fold_random_seed() -> u128 {
+        let mut value: u128 = 0;
+        for byte in env::random_seed() {
+            value = value.wrapping_mul(256).wrapping_add(byte as u128);
         }
+        value
+    }
+}
 
-        // TODO 有待检查
-        if block_index < 1 {
-            block_index += 1;
-        } else if block_index > 253 {
-            block_index -= 1;
+#[near_bindgen]
+impl FungibleTokenReceiver for LinkDrop {
+    /// 接收 NEP-141 代币转账并创建一个以该代币计价的红包，`msg` 为 `FtRedbagMsg` 的 JSON 编码
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        let redbag_msg: FtRedbagMsg =
+            near_sdk::serde_json::from_str(&msg).expect("红包参数解析失败");
+
+        if let Some(expires_at) = redbag_msg.expires_at {
+            assert!(expires_at > env::block_timestamp(), "expires_at must be in the future");
         }
+        assert!(
+            redbag_msg.count >= 1 && redbag_msg.count <= amount.0,
+            "count must be between 1 and the transferred amount"
+        );
+
+        let token_id = env::predecessor_account_id();
+
+        // 大额红包若发送人配置了多签，同样需转为提案、等待 M-of-N 批准，否则换成代币转账即可绕过
+        // send_redbag 里对原生 NEAR 设下的门槛检查
+        if amount.0 >= MULTISIG_BALANCE_THRESHOLD {
+            if let Some(config) = self.multisig_config.get(&sender_id) {
+                let action = PendingAction::SendRedbag {
+                    public_key: redbag_msg.public_key,
+                    count: redbag_msg.count,
+                    mode: redbag_msg.mode,
+                    slogan: redbag_msg.slogan,
+                    expires_at: redbag_msg.expires_at,
+                    deposit: amount.0,
+                    token_id: Some(token_id),
+                };
+                self.propose_action(sender_id, action, config);
+                // 代币已转入合约，计入提案押金，待 approve/cancel_action 时再转出
+                return PromiseOrValue::Value(U128(0));
+            }
+        }
+
+        let new_red_info = RedInfo {
+            mode: redbag_msg.mode,
+            count: redbag_msg.count,
+            slogan: redbag_msg.slogan,
+            balance: amount.0,
+            remaining_balance: amount.0,
+            remaining_count: redbag_msg.count,
+            token_id: Some(token_id),
+            expires_at: redbag_msg.expires_at,
+            messages: Vec::new(),
+        };
+
+        self.register_redbag(sender_id, redbag_msg.public_key.clone(), new_red_info);
+
+        let pk: PublicKey = redbag_msg.public_key.into();
+        self.grant_claim_access_key(pk);
 
-        block_length.wrapping_mul(block_index.into())
+        // 全部代币已计入红包，无需退回
+        PromiseOrValue::Value(U128(0))
     }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use std::convert::TryInto;
+    use super::*;
 
     use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env, BlockHeight, PublicKey, VMContext};
+    use near_sdk::{testing_env, BlockHeight, VMContext};
+
+    fn get_context(signer_account_id: AccountId, signer_account_pk: PublicKey, block_timestamp: u64) -> VMContext {
+        VMContext {
+            current_account_id: "redpacket".to_string(),
+            signer_account_id: signer_account_id.clone(),
+            signer_account_pk,
+            predecessor_account_id: signer_account_id,
+            input: vec![],
+            block_index: 0 as BlockHeight,
+            block_timestamp,
+            account_balance: 0,
+            account_locked_balance: 0,
+            storage_usage: 0,
+            attached_deposit: 0,
+            prepaid_gas: 10u64.pow(18),
+            random_seed: vec![1, 2, 3],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    #[test]
+    fn random_amount_last_claimer_gets_everything() {
+        testing_env!(get_context("alice.near".to_string(), vec![0, 1, 2], 0));
+        let contract = LinkDrop::default();
+        assert_eq!(contract.random_amount(1, 357, 1), 357);
+    }
+
+    #[test]
+    fn random_amount_equal_mode_splits_evenly() {
+        testing_env!(get_context("alice.near".to_string(), vec![0, 1, 2], 0));
+        let contract = LinkDrop::default();
+        assert_eq!(contract.random_amount(0, 100, 4), 25);
+        assert_eq!(contract.random_amount(0, 1, 1), 1);
+    }
+
+    #[test]
+    fn random_amount_never_underflows_and_respects_bounds() {
+        testing_env!(get_context("alice.near".to_string(), vec![0, 1, 2], 0));
+        let contract = LinkDrop::default();
+
+        let mut remaining_balance: u128 = 1_000;
+        let mut remaining_count: u128 = 7;
+
+        while remaining_count > 0 {
+            let amount = contract.random_amount(1, remaining_balance, remaining_count);
+            assert!(amount >= MIN_CLAIM_AMOUNT, "每个领取人必须分到至少 MIN_CLAIM_AMOUNT");
+            assert!(amount <= remaining_balance, "分配金额不能超过剩余总额");
+
+            remaining_balance -= amount;
+            remaining_count -= 1;
+        }
+
+        assert_eq!(remaining_balance, 0, "最后一人应拿走全部剩余金额，不应留下零头");
+    }
+
+    #[test]
+    fn claim_last_claimer_empties_the_packet() {
+        let redbag_pk = Base58PublicKey(vec![5, 5, 5]);
+        let pk: PublicKey = redbag_pk.clone().into();
+        testing_env!(get_context("alice.near".to_string(), pk.clone(), 0));
+
+        let mut contract = LinkDrop::default();
+        contract.red_info.insert(
+            &pk,
+            &RedInfo {
+                mode: 1,
+                count: 1,
+                slogan: "hi".to_string(),
+                balance: 1000,
+                remaining_balance: 1000,
+                remaining_count: 1,
+                token_id: None,
+                expires_at: None,
+                messages: Vec::new(),
+            },
+        );
+        contract.sender_redbag.insert(&"alice.near".to_string(), &vec![redbag_pk]);
+
+        contract.claim("bob.near".to_string());
+
+        let updated = contract.red_info.get(&pk).expect("红包记录应仍在（押金已发放但记录未删除）");
+        assert_eq!(updated.remaining_count, 0);
+        assert_eq!(updated.remaining_balance, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "该用户已领取过")]
+    fn claim_rejects_repeat_claimer_even_with_capacity_left() {
+        let redbag_pk = Base58PublicKey(vec![5, 5, 5]);
+        let pk: PublicKey = redbag_pk.clone().into();
+        testing_env!(get_context("alice.near".to_string(), pk.clone(), 0));
+
+        let mut contract = LinkDrop::default();
+        contract.red_info.insert(
+            &pk,
+            &RedInfo {
+                mode: 0,
+                count: 2,
+                slogan: "hi".to_string(),
+                balance: 1000,
+                remaining_balance: 1000,
+                remaining_count: 2,
+                token_id: None,
+                expires_at: None,
+                messages: Vec::new(),
+            },
+        );
+        contract.sender_redbag.insert(&"alice.near".to_string(), &vec![redbag_pk]);
+
+        contract.claim("bob.near".to_string());
+        contract.claim("bob.near".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "红包尚未过期")]
+    fn refund_expired_before_expiry_panics() {
+        let alice = "alice.near".to_string();
+        testing_env!(get_context(alice.clone(), vec![9, 9, 9], 100));
+
+        let mut contract = LinkDrop::default();
+        let redbag_pk = Base58PublicKey(vec![7, 7, 7]);
+        contract.red_info.insert(
+            &redbag_pk.clone().into(),
+            &RedInfo {
+                mode: 0,
+                count: 1,
+                slogan: "soon".to_string(),
+                balance: 10,
+                remaining_balance: 10,
+                remaining_count: 1,
+                token_id: None,
+                expires_at: Some(200),
+                messages: Vec::new(),
+            },
+        );
+        contract.sender_redbag.insert(&alice, &vec![redbag_pk.clone()]);
+
+        contract.refund_expired(redbag_pk);
+    }
+
+    #[test]
+    fn refund_expired_exactly_at_expiry_succeeds() {
+        let alice = "alice.near".to_string();
+        testing_env!(get_context(alice.clone(), vec![9, 9, 9], 200));
+
+        let mut contract = LinkDrop::default();
+        let redbag_pk = Base58PublicKey(vec![7, 7, 7]);
+        contract.red_info.insert(
+            &redbag_pk.clone().into(),
+            &RedInfo {
+                mode: 0,
+                count: 1,
+                slogan: "soon".to_string(),
+                balance: 10,
+                remaining_balance: 10,
+                remaining_count: 1,
+                token_id: None,
+                expires_at: Some(200),
+                messages: Vec::new(),
+            },
+        );
+        contract.sender_redbag.insert(&alice, &vec![redbag_pk.clone()]);
+
+        contract.refund_expired(redbag_pk.clone());
+
+        assert!(contract.red_info.get(&redbag_pk.into()).is_none(), "红包记录应已被移除");
+    }
+
+    #[test]
+    fn set_multisig_config_bootstraps_then_requires_approval_to_change() {
+        let alice = "alice.near".to_string();
+        testing_env!(get_context(alice, vec![9, 9, 9], 0));
+
+        let mut contract = LinkDrop::default();
+        let signer_a = Base58PublicKey(vec![1; 33]);
+        let signer_b = Base58PublicKey(vec![2; 33]);
+
+        // 首次配置（尚无规则）直接生效
+        assert!(contract.set_multisig_config(vec![signer_a.clone(), signer_b.clone()], 2).is_none());
+
+        // 修改已有规则必须走提案流程
+        let signer_c = Base58PublicKey(vec![3; 33]);
+        assert!(contract.set_multisig_config(vec![signer_a, signer_b, signer_c], 2).is_some());
+    }
+
+    #[test]
+    fn approve_reaches_threshold_and_executes_revoke() {
+        let alice = "alice.near".to_string();
+        testing_env!(get_context(alice.clone(), vec![9, 9, 9], 0));
+
+        let mut contract = LinkDrop::default();
+        contract.multisig_config.insert(
+            &alice,
+            &MultisigConfig { signers: vec![vec![1; 33], vec![2; 33]], threshold: 2 },
+        );
+
+        let redbag_pk = Base58PublicKey(vec![7, 7, 7]);
+        contract.red_info.insert(
+            &redbag_pk.clone().into(),
+            &RedInfo {
+                mode: 0,
+                count: 1,
+                slogan: "hi".to_string(),
+                balance: 500,
+                remaining_balance: 500,
+                remaining_count: 1,
+                token_id: None,
+                expires_at: None,
+                messages: Vec::new(),
+            },
+        );
+        contract.sender_redbag.insert(&alice, &vec![redbag_pk.clone()]);
+
+        let action_hash = contract.propose_revoke(redbag_pk);
+
+        testing_env!(get_context(alice.clone(), vec![1; 33], 0));
+        contract.approve(action_hash.clone());
+        assert!(contract.pending_actions.get(&action_hash).is_some(), "未达 threshold 前提案应仍然存在");
+
+        testing_env!(get_context(alice, vec![2; 33], 0));
+        contract.approve(action_hash.clone());
+        assert!(contract.pending_actions.get(&action_hash).is_none(), "达到 threshold 后提案应被清除并执行");
+    }
+
+    #[test]
+    #[should_panic(expected = "不是该提案的授权签名人")]
+    fn approve_rejects_signer_removed_from_live_config_since_proposal() {
+        let alice = "alice.near".to_string();
+        testing_env!(get_context(alice.clone(), vec![9, 9, 9], 0));
+
+        let mut contract = LinkDrop::default();
+        contract.multisig_config.insert(
+            &alice,
+            &MultisigConfig { signers: vec![vec![1; 33], vec![2; 33]], threshold: 2 },
+        );
+
+        let redbag_pk = Base58PublicKey(vec![7, 7, 7]);
+        contract.red_info.insert(
+            &redbag_pk.clone().into(),
+            &RedInfo {
+                mode: 0,
+                count: 1,
+                slogan: "hi".to_string(),
+                balance: 10,
+                remaining_balance: 10,
+                remaining_count: 1,
+                token_id: None,
+                expires_at: None,
+                messages: Vec::new(),
+            },
+        );
+        contract.sender_redbag.insert(&alice, &vec![redbag_pk.clone()]);
+
+        let revoke_hash = contract.propose_revoke(redbag_pk);
+
+        // 提案发出后规则被改了（绕开提案流程直接改底层存储，模拟 SetConfig 提案已生效）：signer_b 被踢出
+        contract.multisig_config.insert(&alice, &MultisigConfig { signers: vec![vec![1; 33]], threshold: 1 });
+
+        // signer_b 曾是该提案的合法签名人，但 approve 必须按当前规则重新校验，而不是沿用旧快照
+        testing_env!(get_context(alice, vec![2; 33], 0));
+        contract.approve(revoke_hash);
+    }
+
+    #[test]
+    fn decode_message_chunk_validates_header_and_order() {
+        let mut chunk = MESSAGE_CHUNK_COOKIE.to_vec();
+        chunk.push(0);
+        let payload = b"hello";
+        chunk.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+        chunk.extend_from_slice(payload);
+
+        let decoded = LinkDrop::decode_message_chunk(&chunk, 0);
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    #[should_panic(expected = "消息分片顺序错误")]
+    fn decode_message_chunk_rejects_out_of_order_index() {
+        let mut chunk = MESSAGE_CHUNK_COOKIE.to_vec();
+        chunk.push(1);
+        chunk.extend_from_slice(&0u16.to_be_bytes());
+
+        LinkDrop::decode_message_chunk(&chunk, 0);
+    }
 }